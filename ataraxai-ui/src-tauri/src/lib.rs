@@ -5,11 +5,45 @@
 )]
 
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, VecDeque};
+use std::fs::{File, OpenOptions};
+use std::future::Future;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Mutex;
-use std::time::Duration;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 use tauri::{async_runtime, AppHandle, Emitter, Manager, State, WindowEvent};
 use tauri_plugin_shell::process::{CommandChild, CommandEvent};
 use tauri_plugin_shell::ShellExt;
+use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
+
+const DEFAULT_HANDSHAKE_TIMEOUT_SECS: u64 = 120;
+const DEFAULT_HANDSHAKE_POLL_MS: u64 = 1000;
+const HTTP_CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+const ARTIFACT_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(30 * 60);
+const DOWNLOAD_PROGRESS_INTERVAL: Duration = Duration::from_millis(250);
+const MAX_ERROR_BODY_CHARS: usize = 500;
+const RUNTIME_INFO_CACHE_TTL: Duration = Duration::from_secs(10);
+const DEFAULT_SIDECAR_LOG_MAX_BYTES: u64 = 5 * 1024 * 1024;
+const SECRET_HEADER_MARKERS: &[&str] = &["auth", "key", "token", "secret", "cookie", "password"];
+const EVENT_REPLAY_CAPACITY: usize = 100;
+const HEALTH_HISTORY_CAPACITY: usize = 20;
+const REPLAYABLE_EVENTS: &[&str] = &[
+    "backend-ready",
+    "sidecar-error",
+    "sidecar-missing",
+    "sidecar-integrity-failed",
+];
+const EMBEDDED_SIDECAR_SHA256: Option<&str> = option_env!("ATARAX_SIDECAR_SHA256");
+
+#[cfg(feature = "sidecar-integrity")]
+const _: () = assert!(
+    EMBEDDED_SIDECAR_SHA256.is_some(),
+    "the sidecar-integrity feature requires ATARAX_SIDECAR_SHA256 to be set at build time"
+);
 
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -20,92 +54,1920 @@ struct ApiInfo {
 }
 
 
-#[derive(Debug, Default)]
-struct ApiState(Mutex<Option<ApiInfo>>);
+#[derive(Debug, Clone, Serialize)]
+struct DiagnosticStep {
+    name: String,
+    passed: bool,
+    duration_ms: u64,
+    detail: String,
+}
+
+
+#[derive(Debug, Default, Clone, Serialize)]
+struct DiagnosticsReport {
+    success: bool,
+    reached_step: String,
+    handshake: HandshakeSettings,
+    steps: Vec<DiagnosticStep>,
+}
+
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct HandshakeSettings {
+    timeout_secs: u64,
+    poll_ms: u64,
+}
+
+
+#[derive(Debug, Default, Clone, Copy)]
+struct StartupPhases {
+    started: Option<Instant>,
+    spawned: Option<Instant>,
+    first_stdout: Option<Instant>,
+    handshake: Option<Instant>,
+    health_confirmed: Option<Instant>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+struct StartupTimingsReport {
+    spawn_ms: Option<u64>,
+    first_stdout_ms: Option<u64>,
+    handshake_ms: Option<u64>,
+    health_ms: Option<u64>,
+    total_ms: Option<u64>,
+}
+
+
+#[derive(Debug, Default, Clone, Serialize)]
+struct BackendRuntimeInfo {
+    model: Option<String>,
+    context_window: Option<u64>,
+    device: Option<String>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+struct IntegrityReport {
+    path: String,
+    actual_sha256: String,
+    expected_sha256: Option<String>,
+    matches: Option<bool>,
+}
+
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+struct HttpClientConfig {
+    pool_max_idle_per_host: Option<usize>,
+    pool_idle_timeout_secs: Option<u64>,
+    tcp_keepalive_secs: Option<u64>,
+    #[serde(default)]
+    http2_prior_knowledge: bool,
+    user_agent: Option<String>,
+    #[serde(default)]
+    custom_headers: HashMap<String, String>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+struct DownloadProgress {
+    url: String,
+    bytes_downloaded: u64,
+    total_bytes: Option<u64>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+struct ReplayableEvent {
+    sequence: u64,
+    event: String,
+    payload: serde_json::Value,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+struct HealthCheckRecord {
+    checked_at_secs: u64,
+    source: String,
+    port: u16,
+    healthy: bool,
+    duration_ms: u64,
+    detail: String,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+struct BackendReady {
+    port: u16,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+struct SupportInfo {
+    app_version: String,
+    os: String,
+    arch: String,
+    handshake: HandshakeSettings,
+    http_client: HttpClientConfig,
+    integrity_policy: String,
+    sidecar_log_path: Option<String>,
+    backend_port: Option<u16>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+struct SupportBundle {
+    path: String,
+    excluded: Vec<String>,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+struct ConfigIssue {
+    setting: String,
+    severity: String,
+    message: String,
+}
+
+
+#[derive(Debug, Clone, Serialize)]
+struct SidecarMissing {
+    path: String,
+    platform: String,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IntegrityPolicy {
+    Off,
+    Warn,
+    Enforce,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HandshakeUpdate {
+    First,
+    Repeated,
+    Changed,
+}
+
+
+#[derive(Debug, Default)]
+struct ApiState(Mutex<Option<ApiInfo>>);
+
+pub struct ApiProcess(Mutex<Option<CommandChild>>);
+
+#[derive(Debug, Default)]
+struct HandshakeConfig(Mutex<HandshakeSettings>);
+
+#[derive(Debug, Default)]
+struct StartupTimings(Mutex<StartupPhases>);
+
+#[derive(Debug)]
+struct LogCaptureFile {
+    path: PathBuf,
+    writer: BufWriter<File>,
+}
+
+#[derive(Debug, Default)]
+struct SidecarLogCapture(Mutex<Option<LogCaptureFile>>);
+
+#[derive(Debug)]
+struct RotatingLogFile {
+    path: PathBuf,
+    max_bytes: u64,
+    file: File,
+    written: u64,
+}
+
+#[derive(Debug, Default)]
+struct SidecarLogFile(Mutex<Option<RotatingLogFile>>);
+
+struct HttpClient {
+    config: Mutex<HttpClientConfig>,
+    client: Mutex<reqwest::Client>,
+}
+
+#[derive(Debug, Default)]
+struct ShutdownSignal(CancellationToken);
+
+#[derive(Debug, Default)]
+struct EventReplayBuffer(Mutex<(u64, VecDeque<ReplayableEvent>)>);
+
+#[derive(Debug, Default)]
+struct RuntimeInfoCache(Mutex<Option<(Instant, BackendRuntimeInfo)>>);
+
+#[derive(Debug, Default)]
+struct AttachedBackend(Mutex<bool>);
+
+#[derive(Debug, Default)]
+struct HealthHistory(Mutex<VecDeque<HealthCheckRecord>>);
+
+#[derive(Debug, Default)]
+struct SidecarHashCache(Mutex<Option<(PathBuf, SystemTime, String)>>);
+
+impl ApiState {
+    fn set_info(&self, info: ApiInfo) {
+        let mut guard = self.0.lock().unwrap();
+        *guard = Some(info);
+    }
+
+    fn get_info(&self) -> Option<ApiInfo> {
+        let guard = self.0.lock().unwrap();
+        guard.clone()
+    }
+
+    fn clear_info(&self) {
+        let mut guard = self.0.lock().unwrap();
+        *guard = None;
+    }
+}
+
+impl Default for HandshakeSettings {
+    fn default() -> Self {
+        HandshakeSettings {
+            timeout_secs: DEFAULT_HANDSHAKE_TIMEOUT_SECS,
+            poll_ms: DEFAULT_HANDSHAKE_POLL_MS,
+        }
+    }
+}
+
+impl HandshakeSettings {
+    fn from_env() -> Self {
+        Self::from_vars(&env_vars())
+    }
+
+    fn from_vars(env: &HashMap<String, String>) -> Self {
+        let parse = |key: &str, default: u64| {
+            env.get(key)
+                .and_then(|value| parse_positive(value))
+                .unwrap_or(default)
+        };
+
+        HandshakeSettings {
+            timeout_secs: parse("ATARAX_HANDSHAKE_TIMEOUT_SECS", DEFAULT_HANDSHAKE_TIMEOUT_SECS),
+            poll_ms: parse("ATARAX_HANDSHAKE_POLL_MS", DEFAULT_HANDSHAKE_POLL_MS),
+        }
+    }
+}
+
+impl HandshakeConfig {
+    fn get(&self) -> HandshakeSettings {
+        *self.0.lock().unwrap()
+    }
+
+    fn set(&self, settings: HandshakeSettings) {
+        *self.0.lock().unwrap() = settings;
+    }
+}
+
+impl StartupTimings {
+    fn update(&self, mark: impl FnOnce(&mut StartupPhases)) {
+        let mut guard = self.0.lock().unwrap();
+        mark(&mut guard);
+    }
+
+    fn report(&self) -> StartupTimingsReport {
+        let phases = *self.0.lock().unwrap();
+        let between = |from: Option<Instant>, to: Option<Instant>| match (from, to) {
+            (Some(from), Some(to)) => Some(to.duration_since(from).as_millis() as u64),
+            _ => None,
+        };
+
+        StartupTimingsReport {
+            spawn_ms: between(phases.started, phases.spawned),
+            first_stdout_ms: between(phases.spawned, phases.first_stdout),
+            handshake_ms: between(phases.first_stdout, phases.handshake),
+            health_ms: between(phases.handshake, phases.health_confirmed),
+            total_ms: between(phases.started, phases.health_confirmed),
+        }
+    }
+}
+
+impl SidecarLogCapture {
+    fn start(&self, path: PathBuf) -> Result<(), String> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(|e| format!("Failed to open log file {:?}: {}", path, e))?;
+
+        let mut guard = self.0.lock().unwrap();
+        if let Some(mut previous) = guard.take() {
+            let _ = previous.writer.flush();
+        }
+        *guard = Some(LogCaptureFile {
+            path,
+            writer: BufWriter::new(file),
+        });
+        Ok(())
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        self.0.lock().unwrap().as_ref().map(|capture| capture.path.clone())
+    }
+
+    fn stop(&self) -> Option<PathBuf> {
+        let mut capture = self.0.lock().unwrap().take()?;
+        if let Err(e) = capture.writer.flush() {
+            eprintln!("Failed to flush sidecar log capture: {}", e);
+        }
+        Some(capture.path)
+    }
+
+    fn write_line(&self, stream: &str, line: &str) {
+        let mut guard = self.0.lock().unwrap();
+        let Some(capture) = guard.as_mut() else {
+            return;
+        };
+
+        // Sidecar output is low volume, so flush every line to keep a crash
+        // traceback on disk even if no further output arrives.
+        let result = writeln!(capture.writer, "[{}] {}", stream, line).and_then(|_| capture.writer.flush());
+        if let Err(e) = result {
+            eprintln!("Failed to write sidecar log capture to {:?}: {}", capture.path, e);
+        }
+    }
+}
+
+impl RotatingLogFile {
+    fn open(path: PathBuf, max_bytes: u64) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(RotatingLogFile {
+            path,
+            max_bytes,
+            file,
+            written,
+        })
+    }
+
+    fn rotate(&mut self) -> std::io::Result<()> {
+        let rotated_path = self.path.with_extension("log.1");
+        if rotated_path.exists() {
+            std::fs::remove_file(&rotated_path)?;
+        }
+        std::fs::rename(&self.path, &rotated_path)?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+
+    fn write_line(&mut self, stream: &str, line: &str) -> std::io::Result<()> {
+        let entry = format!("[{}] {}\n", stream, line);
+        if self.written > 0 && self.written + entry.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        self.file.write_all(entry.as_bytes())?;
+        self.written += entry.len() as u64;
+        Ok(())
+    }
+}
+
+impl SidecarLogFile {
+    fn from_env(app_handle: &AppHandle) -> Self {
+        let enabled = std::env::var("ATARAX_SIDECAR_LOG")
+            .map(|value| parse_flag(&value))
+            .unwrap_or(false);
+        if !enabled {
+            return SidecarLogFile::default();
+        }
+
+        let max_bytes = std::env::var("ATARAX_SIDECAR_LOG_MAX_BYTES")
+            .ok()
+            .and_then(|value| parse_positive(&value))
+            .unwrap_or(DEFAULT_SIDECAR_LOG_MAX_BYTES);
+
+        let log_file = app_handle
+            .path()
+            .app_log_dir()
+            .map_err(|e| e.to_string())
+            .and_then(|dir| {
+                RotatingLogFile::open(dir.join("sidecar.log"), max_bytes).map_err(|e| e.to_string())
+            });
+
+        match log_file {
+            Ok(log_file) => {
+                println!("Writing sidecar logs to: {:?}", log_file.path);
+                SidecarLogFile(Mutex::new(Some(log_file)))
+            }
+            Err(e) => {
+                eprintln!("Failed to open sidecar log file: {}", e);
+                SidecarLogFile::default()
+            }
+        }
+    }
+
+    fn path(&self) -> Option<PathBuf> {
+        self.0.lock().unwrap().as_ref().map(|log_file| log_file.path.clone())
+    }
+
+    fn write_line(&self, stream: &str, line: &str) {
+        if let Some(log_file) = self.0.lock().unwrap().as_mut() {
+            if let Err(e) = log_file.write_line(stream, line) {
+                eprintln!("Failed to write sidecar log file {:?}: {}", log_file.path, e);
+            }
+        }
+    }
+}
+
+trait ClientBuilderSettings: Sized {
+    fn pool_max_idle_per_host(self, max_idle: usize) -> Self;
+    fn pool_idle_timeout(self, idle_timeout: Duration) -> Self;
+    fn tcp_keepalive(self, keepalive: Duration) -> Self;
+    fn http2_prior_knowledge(self) -> Self;
+    fn user_agent(self, user_agent: &str) -> Self;
+    fn default_headers(self, headers: reqwest::header::HeaderMap) -> Self;
+}
+
+impl ClientBuilderSettings for reqwest::ClientBuilder {
+    fn pool_max_idle_per_host(self, max_idle: usize) -> Self {
+        reqwest::ClientBuilder::pool_max_idle_per_host(self, max_idle)
+    }
+
+    fn pool_idle_timeout(self, idle_timeout: Duration) -> Self {
+        reqwest::ClientBuilder::pool_idle_timeout(self, idle_timeout)
+    }
+
+    fn tcp_keepalive(self, keepalive: Duration) -> Self {
+        reqwest::ClientBuilder::tcp_keepalive(self, keepalive)
+    }
+
+    fn http2_prior_knowledge(self) -> Self {
+        reqwest::ClientBuilder::http2_prior_knowledge(self)
+    }
+
+    fn user_agent(self, user_agent: &str) -> Self {
+        reqwest::ClientBuilder::user_agent(self, user_agent)
+    }
+
+    fn default_headers(self, headers: reqwest::header::HeaderMap) -> Self {
+        reqwest::ClientBuilder::default_headers(self, headers)
+    }
+}
+
+impl HttpClientConfig {
+    fn from_env() -> Self {
+        Self::from_vars(&env_vars())
+    }
+
+    fn from_vars(env: &HashMap<String, String>) -> Self {
+        let value = |key: &str| env.get(key).map(String::as_str);
+
+        HttpClientConfig {
+            pool_max_idle_per_host: value("ATARAX_HTTP_POOL_MAX_IDLE_PER_HOST").and_then(parse_count),
+            pool_idle_timeout_secs: value("ATARAX_HTTP_POOL_IDLE_TIMEOUT_SECS").and_then(parse_positive),
+            tcp_keepalive_secs: value("ATARAX_HTTP_TCP_KEEPALIVE_SECS").and_then(parse_positive),
+            http2_prior_knowledge: value("ATARAX_HTTP2_PRIOR_KNOWLEDGE").is_some_and(parse_flag),
+            user_agent: value("ATARAX_HTTP_USER_AGENT").and_then(parse_user_agent),
+            custom_headers: HashMap::new(),
+        }
+    }
+
+    fn header_map(&self) -> Result<reqwest::header::HeaderMap, String> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in &self.custom_headers {
+            let header_name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| format!("Invalid header name: {:?}", name))?;
+            if header_name == reqwest::header::AUTHORIZATION {
+                return Err("The Authorization header is set from the backend token and cannot be overridden.".into());
+            }
+            let header_value = reqwest::header::HeaderValue::from_str(value)
+                .map_err(|_| format!("Invalid value for header {:?}", name))?;
+            headers.insert(header_name, header_value);
+        }
+        Ok(headers)
+    }
+
+    fn redacted(&self) -> Self {
+        let mut config = self.clone();
+        for (name, value) in config.custom_headers.iter_mut() {
+            let lower = name.to_lowercase();
+            if SECRET_HEADER_MARKERS.iter().any(|marker| lower.contains(marker)) {
+                *value = "<redacted>".to_string();
+            }
+        }
+        config
+    }
+
+    fn apply<B: ClientBuilderSettings>(&self, mut builder: B) -> Result<B, String> {
+        if let Some(max_idle) = self.pool_max_idle_per_host {
+            builder = builder.pool_max_idle_per_host(max_idle);
+        }
+        if let Some(idle_timeout) = self.pool_idle_timeout_secs {
+            builder = builder.pool_idle_timeout(Duration::from_secs(idle_timeout));
+        }
+        if let Some(keepalive) = self.tcp_keepalive_secs {
+            builder = builder.tcp_keepalive(Duration::from_secs(keepalive));
+        }
+        if self.http2_prior_knowledge {
+            builder = builder.http2_prior_knowledge();
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        Ok(builder.default_headers(self.header_map()?))
+    }
+}
+
+impl HttpClient {
+    fn new(config: HttpClientConfig) -> Result<Self, String> {
+        let client = build_http_client(&config)?;
+        Ok(HttpClient {
+            config: Mutex::new(config),
+            client: Mutex::new(client),
+        })
+    }
+
+    fn client(&self) -> reqwest::Client {
+        self.client.lock().unwrap().clone()
+    }
+
+    fn config(&self) -> HttpClientConfig {
+        self.config.lock().unwrap().clone()
+    }
+
+    fn reconfigure(&self, config: HttpClientConfig) -> Result<(), String> {
+        let client = build_http_client(&config)?;
+        *self.client.lock().unwrap() = client;
+        *self.config.lock().unwrap() = config;
+        Ok(())
+    }
+}
+
+impl EventReplayBuffer {
+    fn record(&self, event: &str, payload: serde_json::Value) {
+        let mut guard = self.0.lock().unwrap();
+        let (next_sequence, events) = &mut *guard;
+        *next_sequence += 1;
+        if events.len() == EVENT_REPLAY_CAPACITY {
+            events.pop_front();
+        }
+        events.push_back(ReplayableEvent {
+            sequence: *next_sequence,
+            event: event.to_string(),
+            payload,
+        });
+    }
+
+    fn since(&self, sequence: Option<u64>) -> Vec<ReplayableEvent> {
+        let guard = self.0.lock().unwrap();
+        guard
+            .1
+            .iter()
+            .filter(|event| sequence.is_none_or(|since| event.sequence > since))
+            .cloned()
+            .collect()
+    }
+}
+
+impl HealthHistory {
+    fn record(&self, source: &str, port: u16, duration: Duration, result: &Result<String, String>) {
+        let checked_at_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_secs())
+            .unwrap_or_default();
+        let (healthy, detail) = match result {
+            Ok(message) => (true, message.clone()),
+            Err(e) => (false, e.clone()),
+        };
+
+        let mut history = self.0.lock().unwrap();
+        if history.len() == HEALTH_HISTORY_CAPACITY {
+            history.pop_front();
+        }
+        history.push_back(HealthCheckRecord {
+            checked_at_secs,
+            source: source.to_string(),
+            port,
+            healthy,
+            duration_ms: duration.as_millis() as u64,
+            detail,
+        });
+    }
+
+    fn recent(&self) -> Vec<HealthCheckRecord> {
+        self.0.lock().unwrap().iter().cloned().collect()
+    }
+}
+
+impl SidecarHashCache {
+    fn get(&self, path: &Path, modified: SystemTime) -> Option<String> {
+        let guard = self.0.lock().unwrap();
+        match guard.as_ref() {
+            Some((cached_path, cached_modified, hash)) if cached_path == path && *cached_modified == modified => {
+                Some(hash.clone())
+            }
+            _ => None,
+        }
+    }
+
+    fn store(&self, path: PathBuf, modified: SystemTime, hash: String) {
+        *self.0.lock().unwrap() = Some((path, modified, hash));
+    }
+
+    fn clear(&self) {
+        self.0.lock().unwrap().take();
+    }
+}
+
+impl IntegrityPolicy {
+    fn from_env() -> Self {
+        Self::from_value(std::env::var("ATARAX_SIDECAR_INTEGRITY").ok().as_deref())
+    }
+
+    fn from_value(value: Option<&str>) -> Self {
+        match value.and_then(Self::parse) {
+            Some(policy) => policy,
+            None if cfg!(feature = "sidecar-integrity") => IntegrityPolicy::Enforce,
+            None => IntegrityPolicy::Off,
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "off" => Some(IntegrityPolicy::Off),
+            "warn" => Some(IntegrityPolicy::Warn),
+            "enforce" => Some(IntegrityPolicy::Enforce),
+            _ => None,
+        }
+    }
+}
+
+impl BackendRuntimeInfo {
+    fn from_json(body: &serde_json::Value) -> Self {
+        let fields = body.get("data").unwrap_or(body);
+        let text = |key: &str| fields.get(key).and_then(|v| v.as_str()).map(str::to_string);
+
+        BackendRuntimeInfo {
+            model: text("model"),
+            context_window: fields.get("context_window").and_then(|v| v.as_u64()),
+            device: text("device"),
+        }
+    }
+}
+
+impl DiagnosticsReport {
+    fn record(&mut self, name: &str, started: Instant, result: Result<String, String>) -> bool {
+        let passed = result.is_ok();
+        let detail = result.unwrap_or_else(|e| e);
+        self.reached_step = name.to_string();
+        self.steps.push(DiagnosticStep {
+            name: name.to_string(),
+            passed,
+            duration_ms: started.elapsed().as_millis() as u64,
+            detail,
+        });
+        passed
+    }
+}
+
+
+fn env_vars() -> HashMap<String, String> {
+    std::env::vars_os()
+        .filter_map(|(key, value)| Some((key.into_string().ok()?, value.into_string().ok()?)))
+        .collect()
+}
+
+
+fn parse_positive(value: &str) -> Option<u64> {
+    value.trim().parse::<u64>().ok().filter(|value| *value > 0)
+}
+
+
+fn parse_count(value: &str) -> Option<usize> {
+    value.trim().parse().ok()
+}
+
+
+fn parse_flag(value: &str) -> bool {
+    let value = value.trim();
+    value == "1" || value.eq_ignore_ascii_case("true")
+}
+
+
+fn parse_user_agent(value: &str) -> Option<String> {
+    reqwest::header::HeaderValue::from_str(value)
+        .ok()
+        .map(|_| value.to_string())
+}
+
+
+fn sidecar_executable_name() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "api.exe"
+    } else {
+        "api"
+    }
+}
+
+
+fn resolve_sidecar_path(app_handle: &AppHandle) -> tauri::Result<PathBuf> {
+    let resource_path = format!("py_src/{}", sidecar_executable_name());
+    app_handle
+        .path()
+        .resolve(&resource_path, tauri::path::BaseDirectory::Resource)
+}
+
+
+async fn until_cancelled<F>(token: CancellationToken, task: F)
+where
+    F: Future<Output = ()>,
+{
+    tokio::select! {
+        _ = token.cancelled() => {}
+        _ = task => {}
+    }
+}
+
+
+fn spawn_background<F>(app_handle: &AppHandle, task: F)
+where
+    F: Future<Output = ()> + Send + 'static,
+{
+    let shutdown: State<ShutdownSignal> = app_handle.state();
+    async_runtime::spawn(until_cancelled(shutdown.0.clone(), task));
+}
+
+
+fn emit_replayable<S: Serialize + Clone>(app_handle: &AppHandle, event: &str, payload: S) {
+    if REPLAYABLE_EVENTS.contains(&event) {
+        let replay_buffer: State<EventReplayBuffer> = app_handle.state();
+        match serde_json::to_value(payload.clone()) {
+            Ok(value) => replay_buffer.record(event, value),
+            Err(e) => eprintln!("Failed to record {} for replay: {}", event, e),
+        }
+    }
+    let _ = app_handle.emit(event, payload);
+}
+
+
+fn report_missing_sidecar(app_handle: &AppHandle, attempted: String) -> String {
+    let message = format!(
+        "Backend binary not bundled: no sidecar executable at {}. Rebuild the backend and package it under py_src.",
+        attempted
+    );
+    emit_replayable(
+        app_handle,
+        "sidecar-missing",
+        SidecarMissing {
+            path: attempted,
+            platform: std::env::consts::OS.to_string(),
+        },
+    );
+    message
+}
+
+
+fn sha256_file(path: &Path) -> std::io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+
+fn expected_sidecar_hash(app_handle: &AppHandle) -> Option<String> {
+    if let Some(hash) = EMBEDDED_SIDECAR_SHA256 {
+        return Some(hash.trim().to_lowercase());
+    }
+
+    let hash_resource = format!("py_src/{}.sha256", sidecar_executable_name());
+    let hash_path = app_handle
+        .path()
+        .resolve(&hash_resource, tauri::path::BaseDirectory::Resource)
+        .ok()?;
+    let contents = std::fs::read_to_string(hash_path).ok()?;
+    contents
+        .split_whitespace()
+        .next()
+        .map(|hash| hash.to_lowercase())
+}
+
+
+async fn check_sidecar_integrity(
+    app_handle: &AppHandle,
+    executable_path: PathBuf,
+) -> Result<IntegrityReport, String> {
+    let expected_sha256 = expected_sidecar_hash(app_handle);
+    let path = executable_path.to_string_lossy().into_owned();
+    let hash_cache: State<SidecarHashCache> = app_handle.state();
+    let modified = std::fs::metadata(&executable_path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+
+    let cached = modified.and_then(|modified| hash_cache.get(&executable_path, modified));
+    let actual_sha256 = match cached {
+        Some(hash) => hash,
+        None => {
+            let target = executable_path.clone();
+            let hash = async_runtime::spawn_blocking(move || sha256_file(&target))
+                .await
+                .map_err(|e| format!("Integrity check task failed: {}", e))?
+                .map_err(|e| format!("Failed to hash sidecar executable {}: {}", path, e))?;
+            if let Some(modified) = modified {
+                hash_cache.store(executable_path, modified, hash.clone());
+            }
+            hash
+        }
+    };
+
+    let matches = expected_sha256
+        .as_ref()
+        .map(|expected| *expected == actual_sha256);
+
+    Ok(IntegrityReport {
+        path,
+        actual_sha256,
+        expected_sha256,
+        matches,
+    })
+}
+
+
+fn build_http_client(config: &HttpClientConfig) -> Result<reqwest::Client, String> {
+    config
+        .apply(reqwest::Client::builder().timeout(HTTP_CLIENT_TIMEOUT))?
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+
+fn backend_url(info: &ApiInfo, path: &str) -> String {
+    format!("http://127.0.0.1:{}{}", info.port, path)
+}
+
+
+fn error_detail(body: &str) -> Option<String> {
+    let body = body.trim();
+    if body.is_empty() {
+        return None;
+    }
+
+    let detail = match serde_json::from_str::<serde_json::Value>(body) {
+        Ok(value) => match value.get("detail") {
+            Some(serde_json::Value::String(detail)) => detail.clone(),
+            Some(detail) => detail.to_string(),
+            None => body.to_string(),
+        },
+        Err(_) => body.to_string(),
+    };
+
+    if detail.chars().count() > MAX_ERROR_BODY_CHARS {
+        let truncated: String = detail.chars().take(MAX_ERROR_BODY_CHARS).collect();
+        Some(format!("{}...", truncated))
+    } else {
+        Some(detail)
+    }
+}
+
+
+async fn fetch_backend_json(
+    client: &reqwest::Client,
+    info: &ApiInfo,
+    path: &str,
+    timeout: Duration,
+) -> Result<serde_json::Value, String> {
+    let response = client
+        .get(backend_url(info, path))
+        .bearer_auth(&info.token)
+        .timeout(timeout)
+        .send()
+        .await
+        .map_err(|e| {
+            if e.is_timeout() {
+                format!("{} did not respond within {:?}", path, timeout)
+            } else {
+                format!("Request to {} failed: {}", path, e)
+            }
+        })?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return Err(match error_detail(&body) {
+            Some(detail) => format!("{} returned status {}: {}", path, status, detail),
+            None => format!("{} returned status {}", path, status),
+        });
+    }
+
+    response
+        .json()
+        .await
+        .map_err(|e| format!("Invalid response from {}: {}", path, e))
+}
+
+
+async fn query_backend(
+    client: &reqwest::Client,
+    info: &ApiInfo,
+    path: &str,
+    timeout: Duration,
+) -> Result<String, String> {
+    let body = fetch_backend_json(client, info, path, timeout).await?;
+
+    Ok(body
+        .get("message")
+        .and_then(|message| message.as_str())
+        .unwrap_or_default()
+        .to_string())
+}
+
+
+#[tauri::command]
+async fn get_api_info(
+    state: State<'_, ApiState>,
+    handshake: State<'_, HandshakeConfig>,
+    shutdown: State<'_, ShutdownSignal>,
+) -> Result<ApiInfo, String> {
+    let settings = handshake.get();
+    let timeout_duration = Duration::from_secs(settings.timeout_secs);
+    let start = std::time::Instant::now();
+    let poll_interval = Duration::from_millis(settings.poll_ms);
+
+    while start.elapsed() < timeout_duration {
+        if let Some(info) = state.get_info() {
+            println!("API connection details acquired after {:?}", start.elapsed());
+            println!("Port: {}, Token: {}", info.port, info.token);
+            return Ok(info);
+        }
+        
+        let elapsed_secs = start.elapsed().as_secs();
+        if elapsed_secs > 0 && elapsed_secs % 5 == 0 {
+            println!("Still waiting for Python backend... ({}s elapsed)", elapsed_secs);
+        }
+        
+        tokio::select! {
+            _ = shutdown.0.cancelled() => {
+                return Err("Application is shutting down.".into());
+            }
+            _ = tokio::time::sleep(poll_interval) => {}
+        }
+    }
+
+    let elapsed = start.elapsed();
+    eprintln!(
+        "API failed to provide connection details after {:?}. Python backend may have crashed or failed to start.",
+        elapsed
+    );
+    
+    Err(format!(
+        "Backend startup timeout ({:.1}s). Check console logs for Python errors.",
+        elapsed.as_secs_f32()
+    ))
+}
+
+#[tauri::command]
+fn stop_python_sidecar(state: State<'_, ApiProcess>) -> Result<(), String> {
+    if let Some(child) = state.0.lock().unwrap().take() {
+        child.kill().map_err(|e| format!("Failed to kill sidecar: {}", e))
+    } else {
+        Err("No sidecar process was running.".into())
+    }
+}
+
+async fn diagnose_backend(
+    pid: Option<u32>,
+    attached: bool,
+    info: Option<ApiInfo>,
+    client: &reqwest::Client,
+    handshake: HandshakeSettings,
+) -> DiagnosticsReport {
+    let mut report = DiagnosticsReport {
+        handshake,
+        ..Default::default()
+    };
+
+    let started = Instant::now();
+    let result = match pid {
+        Some(pid) => Ok(format!("Sidecar process running with pid {}.", pid)),
+        None if attached => Ok("Attached to external backend; no sidecar process is managed.".to_string()),
+        None => Err("No sidecar process is running.".to_string()),
+    };
+    if !report.record("sidecar_process", started, result) {
+        return report;
+    }
+
+    let started = Instant::now();
+    let result = match &info {
+        Some(info) => Ok(format!("Backend listening on port {}.", info.port)),
+        None => Err("Backend has not completed the handshake yet.".to_string()),
+    };
+    if !report.record("handshake", started, result) {
+        return report;
+    }
+    let info = info.unwrap();
+
+    let started = Instant::now();
+    let result = query_backend(client, &info, "/v1/health", HEALTH_CHECK_TIMEOUT).await;
+    if !report.record("http_health", started, result) {
+        return report;
+    }
+
+    let started = Instant::now();
+    let result = query_backend(client, &info, "/v1/status", HEALTH_CHECK_TIMEOUT).await;
+    if !report.record("ping", started, result) {
+        return report;
+    }
+
+    report.success = true;
+    report
+}
+
+#[tauri::command]
+async fn run_diagnostics(
+    state: State<'_, ApiState>,
+    app_handle: AppHandle,
+) -> Result<DiagnosticsReport, String> {
+    let handshake: State<HandshakeConfig> = app_handle.state();
+    let process_state: State<ApiProcess> = app_handle.state();
+    let attached: State<AttachedBackend> = app_handle.state();
+    let http: State<HttpClient> = app_handle.state();
+
+    let health_history: State<HealthHistory> = app_handle.state();
+
+    let pid = process_state.0.lock().unwrap().as_ref().map(|child| child.pid());
+    let attached = *attached.0.lock().unwrap();
+    let info = state.get_info();
+    let report = diagnose_backend(pid, attached, info.clone(), &http.client(), handshake.get()).await;
+
+    if let (Some(info), Some(step)) = (info, report.steps.iter().find(|step| step.name == "http_health")) {
+        let result = if step.passed {
+            Ok(step.detail.clone())
+        } else {
+            Err(step.detail.clone())
+        };
+        health_history.record("diagnostics", info.port, Duration::from_millis(step.duration_ms), &result);
+    }
+    Ok(report)
+}
+
+#[tauri::command]
+fn set_handshake_timeout(
+    timeout_secs: u64,
+    poll_ms: Option<u64>,
+    state: State<'_, HandshakeConfig>,
+) -> Result<HandshakeSettings, String> {
+    if timeout_secs == 0 {
+        return Err("Handshake timeout must be greater than zero.".into());
+    }
+    if poll_ms == Some(0) {
+        return Err("Handshake poll interval must be greater than zero.".into());
+    }
+
+    let settings = HandshakeSettings {
+        timeout_secs,
+        poll_ms: poll_ms.unwrap_or(state.get().poll_ms),
+    };
+    state.set(settings);
+    println!("Handshake settings updated: {:?}", settings);
+    Ok(settings)
+}
+
+
+#[tauri::command]
+fn get_startup_timings(state: State<'_, StartupTimings>) -> StartupTimingsReport {
+    state.report()
+}
+
+
+async fn confirm_backend_health(app_handle: AppHandle, api_info: ApiInfo) {
+    let timings: State<StartupTimings> = app_handle.state();
+    let http: State<HttpClient> = app_handle.state();
+    let health_history: State<HealthHistory> = app_handle.state();
+
+    let started = Instant::now();
+    let result = query_backend(&http.client(), &api_info, "/v1/health", HEALTH_CHECK_TIMEOUT).await;
+    health_history.record("startup", api_info.port, started.elapsed(), &result);
+    match result {
+        Ok(_) => {
+            timings.update(|phases| phases.health_confirmed = Some(Instant::now()));
+            println!("Backend health confirmed. Startup timings: {:?}", timings.report());
+        }
+        Err(e) => eprintln!("Backend health check after handshake failed: {}", e),
+    }
+}
+
+
+async fn warm_up(client: &reqwest::Client, api_info: &ApiInfo) -> Result<String, String> {
+    query_backend(client, api_info, "/v1/health", HEALTH_CHECK_TIMEOUT).await
+}
+
+
+#[tauri::command]
+fn warm_up_connection(app_handle: AppHandle) {
+    let api_state: State<ApiState> = app_handle.state();
+    let Some(api_info) = api_state.get_info() else {
+        println!("Skipping connection warmup: backend has not completed the handshake yet.");
+        return;
+    };
+
+    let task_handle = app_handle.clone();
+    spawn_background(&app_handle, async move {
+        let http: State<HttpClient> = task_handle.state();
+        let health_history: State<HealthHistory> = task_handle.state();
+
+        let started = Instant::now();
+        let result = warm_up(&http.client(), &api_info).await;
+        health_history.record("warmup", api_info.port, started.elapsed(), &result);
+        match result {
+            Ok(_) => println!("Backend connection warmed up."),
+            Err(e) => eprintln!("Backend connection warmup failed: {}", e),
+        }
+    });
+}
+
+
+fn validate_artifact_url(url: &str, info: &ApiInfo) -> Result<reqwest::Url, String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Invalid artifact URL: {}", e))?;
+    let is_local_host = matches!(parsed.host_str(), Some("127.0.0.1") | Some("localhost"));
+
+    if parsed.scheme() != "http" || !is_local_host || parsed.port() != Some(info.port) {
+        return Err(format!(
+            "Refusing to download {}: artifacts can only be fetched from the backend at 127.0.0.1:{}",
+            url, info.port
+        ));
+    }
+    Ok(parsed)
+}
+
+
+fn validate_download_dest(dest: &str) -> Result<PathBuf, String> {
+    let dest_path = PathBuf::from(dest);
+    if !dest_path.is_absolute() {
+        return Err(format!("Download destination must be an absolute path: {}", dest));
+    }
+    if dest_path
+        .components()
+        .any(|component| component == std::path::Component::ParentDir)
+    {
+        return Err(format!("Download destination must not contain '..': {}", dest));
+    }
+    if dest_path.is_dir() {
+        return Err(format!("Download destination is a directory: {}", dest));
+    }
+    match dest_path.parent() {
+        Some(parent) if parent.is_dir() => Ok(dest_path),
+        _ => Err(format!("Download destination folder does not exist: {}", dest)),
+    }
+}
+
+
+async fn stream_artifact(
+    response: &mut reqwest::Response,
+    partial_path: &Path,
+    dest_path: &Path,
+    mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<u64, String> {
+    let mut file = tokio::fs::File::create(partial_path)
+        .await
+        .map_err(|e| format!("Failed to create {:?}: {}", partial_path, e))?;
+
+    let total_bytes = response.content_length();
+    let mut bytes_downloaded: u64 = 0;
+    let mut last_progress = Instant::now();
+
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|e| format!("Artifact download interrupted: {}", e))?
+    {
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| format!("Failed to write {:?}: {}", partial_path, e))?;
+        bytes_downloaded += chunk.len() as u64;
+
+        if last_progress.elapsed() >= DOWNLOAD_PROGRESS_INTERVAL {
+            on_progress(bytes_downloaded, total_bytes);
+            last_progress = Instant::now();
+        }
+    }
+
+    file.flush()
+        .await
+        .map_err(|e| format!("Failed to write {:?}: {}", partial_path, e))?;
+    drop(file);
+    tokio::fs::rename(partial_path, dest_path)
+        .await
+        .map_err(|e| format!("Failed to move download into {:?}: {}", dest_path, e))?;
+
+    on_progress(bytes_downloaded, total_bytes);
+    Ok(bytes_downloaded)
+}
+
+
+async fn fetch_artifact(
+    client: &reqwest::Client,
+    url: reqwest::Url,
+    token: &str,
+    dest_path: &Path,
+    on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<u64, String> {
+    let mut response = client
+        .get(url)
+        .bearer_auth(token)
+        .timeout(ARTIFACT_DOWNLOAD_TIMEOUT)
+        .send()
+        .await
+        .map_err(|e| format!("Artifact download failed: {}", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("Artifact download returned status {}", status));
+    }
+
+    let mut partial_name = dest_path.as_os_str().to_owned();
+    partial_name.push(".part");
+    let partial_path = PathBuf::from(partial_name);
+
+    let result = stream_artifact(&mut response, &partial_path, dest_path, on_progress).await;
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&partial_path).await;
+    }
+    result
+}
+
+
+#[tauri::command]
+async fn download_artifact(
+    url: String,
+    dest: String,
+    state: State<'_, ApiState>,
+    http: State<'_, HttpClient>,
+    app_handle: AppHandle,
+) -> Result<u64, String> {
+    let api_info = state
+        .get_info()
+        .ok_or_else(|| "Backend has not completed the handshake yet.".to_string())?;
+    let artifact_url = validate_artifact_url(&url, &api_info)?;
+    let dest_path = validate_download_dest(&dest)?;
+
+    let bytes_downloaded = fetch_artifact(
+        &http.client(),
+        artifact_url,
+        &api_info.token,
+        &dest_path,
+        |bytes_downloaded, total_bytes| {
+            let _ = app_handle.emit(
+                "artifact-download-progress",
+                DownloadProgress {
+                    url: url.clone(),
+                    bytes_downloaded,
+                    total_bytes,
+                },
+            );
+        },
+    )
+    .await?;
+
+    println!("Downloaded {} bytes from {} to {:?}", bytes_downloaded, url, dest_path);
+    Ok(bytes_downloaded)
+}
+
+
+#[tauri::command]
+fn replay_events(since: Option<u64>, state: State<'_, EventReplayBuffer>) -> Vec<ReplayableEvent> {
+    state.since(since)
+}
+
+
+fn is_cjk(ch: char) -> bool {
+    matches!(
+        ch,
+        '\u{3040}'..='\u{30FF}'
+            | '\u{3400}'..='\u{4DBF}'
+            | '\u{4E00}'..='\u{9FFF}'
+            | '\u{AC00}'..='\u{D7AF}'
+            | '\u{F900}'..='\u{FAFF}'
+    )
+}
+
+
+fn approximate_token_count(text: &str) -> usize {
+    // Short words are usually a single BPE token and longer ones split every
+    // few characters; punctuation marks are tokens of their own. CJK, kana
+    // and Hangul text has no spaces and costs at least a token per character.
+    let mut tokens = 0;
+    let mut word_chars: usize = 0;
+    for ch in text.chars() {
+        if is_cjk(ch) {
+            tokens += word_chars.div_ceil(6) + 1;
+            word_chars = 0;
+            continue;
+        }
+        if ch.is_alphanumeric() {
+            word_chars += 1;
+            continue;
+        }
+        tokens += word_chars.div_ceil(6);
+        word_chars = 0;
+        if !ch.is_whitespace() {
+            tokens += 1;
+        }
+    }
+    tokens + word_chars.div_ceil(6)
+}
+
+
+#[tauri::command]
+fn estimate_tokens(text: String) -> usize {
+    approximate_token_count(&text)
+}
+
+
+fn write_support_bundle(
+    bundle_path: &Path,
+    entries: Vec<(String, Vec<u8>)>,
+) -> zip::result::ZipResult<()> {
+    if let Some(parent) = bundle_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut zip = zip::ZipWriter::new(File::create_new(bundle_path)?);
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated);
+
+    for (name, contents) in entries {
+        zip.start_file(name, options)?;
+        zip.write_all(&contents)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+
+#[tauri::command]
+async fn create_support_bundle(app_handle: AppHandle) -> Result<SupportBundle, String> {
+    let api_state: State<ApiState> = app_handle.state();
+    let handshake: State<HandshakeConfig> = app_handle.state();
+    let http: State<HttpClient> = app_handle.state();
+    let timings: State<StartupTimings> = app_handle.state();
+    let replay_buffer: State<EventReplayBuffer> = app_handle.state();
+    let log_file: State<SidecarLogFile> = app_handle.state();
+    let log_capture: State<SidecarLogCapture> = app_handle.state();
+    let health_history: State<HealthHistory> = app_handle.state();
+
+    let sidecar_log_path = log_file.path();
+    let info = SupportInfo {
+        app_version: app_handle.package_info().version.to_string(),
+        os: std::env::consts::OS.to_string(),
+        arch: std::env::consts::ARCH.to_string(),
+        handshake: handshake.get(),
+        http_client: http.config().redacted(),
+        integrity_policy: format!("{:?}", IntegrityPolicy::from_env()),
+        sidecar_log_path: sidecar_log_path
+            .as_ref()
+            .map(|path| path.to_string_lossy().into_owned()),
+        backend_port: api_state.get_info().map(|info| info.port),
+    };
+
+    let to_json = |value: serde_json::Value| serde_json::to_vec_pretty(&value).unwrap_or_default();
+    let entries = vec![
+        ("info.json".to_string(), to_json(serde_json::json!(info))),
+        ("startup_timings.json".to_string(), to_json(serde_json::json!(timings.report()))),
+        ("events.json".to_string(), to_json(serde_json::json!(replay_buffer.since(None)))),
+        ("health_history.json".to_string(), to_json(serde_json::json!(health_history.recent()))),
+    ];
+
+    // The backend logs user queries and prompt messages at every level, so raw
+    // sidecar logs are left out rather than shipped with message content.
+    let excluded: Vec<String> = sidecar_log_path
+        .into_iter()
+        .chain(log_capture.path())
+        .map(|path| {
+            format!(
+                "{} was not included because backend logs can contain message content.",
+                path.to_string_lossy()
+            )
+        })
+        .collect();
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis())
+        .unwrap_or_default();
+    let bundle_path = app_handle
+        .path()
+        .app_log_dir()
+        .map_err(|e| format!("Failed to resolve the log directory: {}", e))?
+        .join(format!("support-bundle-{}.zip", timestamp));
+
+    let target = bundle_path.clone();
+    async_runtime::spawn_blocking(move || write_support_bundle(&target, entries))
+        .await
+        .map_err(|e| format!("Support bundle task failed: {}", e))?
+        .map_err(|e| format!("Failed to write support bundle {:?}: {}", bundle_path, e))?;
+
+    println!("Support bundle written to: {:?}", bundle_path);
+    Ok(SupportBundle {
+        path: bundle_path.to_string_lossy().into_owned(),
+        excluded,
+    })
+}
+
+
+fn config_issue(issues: &mut Vec<ConfigIssue>, setting: &str, severity: &str, message: String) {
+    issues.push(ConfigIssue {
+        setting: setting.to_string(),
+        severity: severity.to_string(),
+        message,
+    });
+}
+
+
+fn check_positive_setting(issues: &mut Vec<ConfigIssue>, env: &HashMap<String, String>, key: &str) {
+    let Some(value) = env.get(key) else {
+        return;
+    };
+    if parse_positive(value).is_none() {
+        config_issue(
+            issues,
+            key,
+            "error",
+            format!("{} must be a positive integer, got {:?}. The default will be used.", key, value),
+        );
+    }
+}
+
+
+fn collect_config_issues(
+    env: &HashMap<String, String>,
+    settings: HandshakeSettings,
+    sidecar_hash_available: bool,
+    sidecar_log_open: bool,
+) -> Vec<ConfigIssue> {
+    let mut issues = Vec::new();
+
+    for key in [
+        "ATARAX_HANDSHAKE_TIMEOUT_SECS",
+        "ATARAX_HANDSHAKE_POLL_MS",
+        "ATARAX_SIDECAR_LOG_MAX_BYTES",
+        "ATARAX_HTTP_POOL_IDLE_TIMEOUT_SECS",
+        "ATARAX_HTTP_TCP_KEEPALIVE_SECS",
+    ] {
+        check_positive_setting(&mut issues, env, key);
+    }
+
+    if let Some(value) = env.get("ATARAX_HTTP_POOL_MAX_IDLE_PER_HOST") {
+        if parse_count(value).is_none() {
+            config_issue(
+                &mut issues,
+                "ATARAX_HTTP_POOL_MAX_IDLE_PER_HOST",
+                "error",
+                format!("Not a valid connection count: {:?}.", value),
+            );
+        }
+    }
+
+    let http2_prior_knowledge = env
+        .get("ATARAX_HTTP2_PRIOR_KNOWLEDGE")
+        .is_some_and(|value| parse_flag(value));
+    if http2_prior_knowledge {
+        config_issue(
+            &mut issues,
+            "ATARAX_HTTP2_PRIOR_KNOWLEDGE",
+            "warning",
+            "The bundled backend only speaks HTTP/1.1, so HTTP/2 prior knowledge makes every request to it fail."
+                .to_string(),
+        );
+    }
+
+    if let Some(value) = env.get("ATARAX_HTTP_USER_AGENT") {
+        if parse_user_agent(value).is_none() {
+            config_issue(
+                &mut issues,
+                "ATARAX_HTTP_USER_AGENT",
+                "error",
+                format!("User-Agent contains characters that are not allowed in a header: {:?}.", value),
+            );
+        }
+    }
+
+    if settings.poll_ms >= settings.timeout_secs.saturating_mul(1000) {
+        config_issue(
+            &mut issues,
+            "ATARAX_HANDSHAKE_POLL_MS",
+            "warning",
+            format!(
+                "Poll interval ({} ms) is not shorter than the handshake timeout ({} s).",
+                settings.poll_ms, settings.timeout_secs
+            ),
+        );
+    }
+
+    let integrity = env.get("ATARAX_SIDECAR_INTEGRITY");
+    if let Some(value) = integrity {
+        if IntegrityPolicy::parse(value).is_none() {
+            config_issue(
+                &mut issues,
+                "ATARAX_SIDECAR_INTEGRITY",
+                "error",
+                format!("Unknown integrity policy {:?}; expected off, warn or enforce.", value),
+            );
+        }
+    }
+    if IntegrityPolicy::from_value(integrity.map(String::as_str)) != IntegrityPolicy::Off && !sidecar_hash_available {
+        config_issue(
+            &mut issues,
+            "ATARAX_SIDECAR_INTEGRITY",
+            "warning",
+            "Integrity checking is enabled but no expected sidecar hash is embedded or bundled.".to_string(),
+        );
+    }
+
+    let log_enabled = env.get("ATARAX_SIDECAR_LOG").is_some_and(|value| parse_flag(value));
+    if log_enabled && !sidecar_log_open {
+        config_issue(
+            &mut issues,
+            "ATARAX_SIDECAR_LOG",
+            "error",
+            "Sidecar log file is enabled but could not be opened.".to_string(),
+        );
+    }
+
+    match (env.get("ATARAX_ATTACH_PORT"), env.get("ATARAX_ATTACH_TOKEN")) {
+        (Some(port), _) if port.trim().parse::<u16>().is_err() => config_issue(
+            &mut issues,
+            "ATARAX_ATTACH_PORT",
+            "error",
+            format!("Not a valid port: {:?}.", port),
+        ),
+        (Some(_), None) | (None, Some(_)) => config_issue(
+            &mut issues,
+            "ATARAX_ATTACH_PORT",
+            "warning",
+            "ATARAX_ATTACH_PORT and ATARAX_ATTACH_TOKEN must both be set to attach to a running backend."
+                .to_string(),
+        ),
+        _ => {}
+    }
+
+    issues
+}
+
+
+#[tauri::command]
+fn validate_config(
+    handshake: State<'_, HandshakeConfig>,
+    log_file: State<'_, SidecarLogFile>,
+    app_handle: AppHandle,
+) -> Vec<ConfigIssue> {
+    collect_config_issues(
+        &env_vars(),
+        handshake.get(),
+        expected_sidecar_hash(&app_handle).is_some(),
+        log_file.path().is_some(),
+    )
+}
+
+
+#[tauri::command]
+fn get_http_client_config(http: State<'_, HttpClient>) -> HttpClientConfig {
+    http.config()
+}
 
-pub struct ApiProcess(Mutex<Option<CommandChild>>);
 
-impl ApiState {
-    fn set_info(&self, info: ApiInfo) {
-        let mut guard = self.0.lock().unwrap();
-        *guard = Some(info);
-    }
+#[tauri::command]
+async fn set_http_client_config(
+    config: HttpClientConfig,
+    http: State<'_, HttpClient>,
+    state: State<'_, ApiState>,
+) -> Result<(), String> {
+    let previous = http.config();
+    let http2_prior_knowledge = config.http2_prior_knowledge;
+    http.reconfigure(config)?;
+    println!("HTTP client rebuilt with {:?}", http.config().redacted());
 
-    fn get_info(&self) -> Option<ApiInfo> {
-        let guard = self.0.lock().unwrap();
-        guard.clone()
+    // The bundled uvicorn backend only speaks HTTP/1.1, so confirm it still
+    // answers before keeping HTTP/2 prior knowledge.
+    if let (true, Some(api_info)) = (http2_prior_knowledge, state.get_info()) {
+        if let Err(e) = query_backend(&http.client(), &api_info, "/v1/health", HEALTH_CHECK_TIMEOUT).await {
+            http.reconfigure(previous)?;
+            return Err(format!(
+                "The backend did not accept HTTP/2 prior knowledge ({}). The previous HTTP settings were restored.",
+                e
+            ));
+        }
     }
+    Ok(())
 }
 
 
 #[tauri::command]
-async fn get_api_info(state: State<'_, ApiState>) -> Result<ApiInfo, String> {
-    let timeout_duration = Duration::from_secs(120);
-    let start = std::time::Instant::now();
-    let poll_interval = Duration::from_millis(1000); 
-
-    while start.elapsed() < timeout_duration {
-        if let Some(info) = state.get_info() {
-            println!("API connection details acquired after {:?}", start.elapsed());
-            println!("Port: {}, Token: {}", info.port, info.token);
+async fn get_backend_runtime_info(
+    state: State<'_, ApiState>,
+    http: State<'_, HttpClient>,
+    cache: State<'_, RuntimeInfoCache>,
+) -> Result<BackendRuntimeInfo, String> {
+    let cached = cache.0.lock().unwrap().clone();
+    if let Some((fetched_at, info)) = cached {
+        if fetched_at.elapsed() < RUNTIME_INFO_CACHE_TTL {
             return Ok(info);
         }
-        
-        let elapsed_secs = start.elapsed().as_secs();
-        if elapsed_secs > 0 && elapsed_secs % 5 == 0 {
-            println!("Still waiting for Python backend... ({}s elapsed)", elapsed_secs);
+    }
+
+    let api_info = state
+        .get_info()
+        .ok_or_else(|| "Backend has not completed the handshake yet.".to_string())?;
+
+    let runtime_info = match fetch_backend_json(&http.client(), &api_info, "/v1/info", HEALTH_CHECK_TIMEOUT).await {
+        Ok(body) => BackendRuntimeInfo::from_json(&body),
+        Err(e) => {
+            println!("Backend runtime info unavailable: {}", e);
+            BackendRuntimeInfo::default()
         }
-        
-        tokio::time::sleep(poll_interval).await;
+    };
+
+    *cache.0.lock().unwrap() = Some((Instant::now(), runtime_info.clone()));
+    Ok(runtime_info)
+}
+
+
+#[tauri::command]
+fn start_sidecar_log_capture(path: String, state: State<'_, SidecarLogCapture>) -> Result<(), String> {
+    state.start(PathBuf::from(&path))?;
+    println!("Capturing sidecar logs to: {}", path);
+    Ok(())
+}
+
+
+#[tauri::command]
+fn stop_sidecar_log_capture(state: State<'_, SidecarLogCapture>) -> Result<String, String> {
+    match state.stop() {
+        Some(path) => Ok(path.to_string_lossy().into_owned()),
+        None => Err("Sidecar log capture is not running.".into()),
     }
+}
 
-    let elapsed = start.elapsed();
-    eprintln!(
-        "API failed to provide connection details after {:?}. Python backend may have crashed or failed to start.",
-        elapsed
-    );
-    
-    Err(format!(
-        "Backend startup timeout ({:.1}s). Check console logs for Python errors.",
-        elapsed.as_secs_f32()
-    ))
+
+#[tauri::command]
+fn get_sidecar_log_path(state: State<'_, SidecarLogFile>) -> Option<String> {
+    state
+        .path()
+        .map(|path| path.to_string_lossy().into_owned())
 }
 
+
 #[tauri::command]
-fn stop_python_sidecar(state: State<'_, ApiProcess>) -> Result<(), String> {
-    if let Some(child) = state.0.lock().unwrap().take() {
-        child.kill().map_err(|e| format!("Failed to kill sidecar: {}", e))
+async fn verify_sidecar_integrity(app_handle: AppHandle) -> Result<IntegrityReport, String> {
+    let executable_path = resolve_sidecar_path(&app_handle)
+        .map_err(|e| format!("Failed to resolve sidecar path: {}", e))?;
+    check_sidecar_integrity(&app_handle, executable_path).await
+}
+
+
+#[tauri::command]
+async fn reload_sidecar_from_path(path: String, app_handle: AppHandle) -> Result<(), String> {
+    if !cfg!(debug_assertions) {
+        return Err("Reloading the sidecar from a custom path is only available in debug builds.".into());
+    }
+
+    let executable_path = PathBuf::from(&path);
+    if !executable_path.is_file() {
+        return Err(format!("Sidecar executable not found at: {}", path));
+    }
+
+    let api_process_state: State<ApiProcess> = app_handle.state();
+    let api_state: State<ApiState> = app_handle.state();
+    let attached: State<AttachedBackend> = app_handle.state();
+    let hash_cache: State<SidecarHashCache> = app_handle.state();
+
+    if let Some(child) = api_process_state.0.lock().unwrap().take() {
+        println!("Stopping current sidecar before reloading from: {}", path);
+        child
+            .kill()
+            .map_err(|e| format!("Failed to kill sidecar: {}", e))?;
+    }
+    api_state.clear_info();
+    *attached.0.lock().unwrap() = false;
+    hash_cache.clear();
+
+    spawn_python_sidecar(app_handle.clone(), Some(executable_path));
+    Ok(())
+}
+
+
+fn parse_ready_handshake(line: &str) -> Option<ApiInfo> {
+    serde_json::from_str::<ApiInfo>(line.trim())
+        .ok()
+        .filter(|api_info| api_info.status == "ready")
+}
+
+
+fn classify_ready_handshake(
+    handshake_complete: bool,
+    current: Option<&ApiInfo>,
+    announced: &ApiInfo,
+) -> HandshakeUpdate {
+    if !handshake_complete {
+        HandshakeUpdate::First
+    } else if current.is_some_and(|current| {
+        current.port == announced.port && current.token == announced.token
+    }) {
+        HandshakeUpdate::Repeated
     } else {
-        Err("No sidecar process was running.".into())
+        HandshakeUpdate::Changed
+    }
+}
+
+
+fn apply_ready_handshake(
+    api_state: &ApiState,
+    handshake_complete: &mut bool,
+    announced: ApiInfo,
+) -> HandshakeUpdate {
+    let current = api_state.get_info();
+    let update = classify_ready_handshake(*handshake_complete, current.as_ref(), &announced);
+    if update != HandshakeUpdate::Repeated {
+        api_state.set_info(announced);
+        *handshake_complete = true;
+    }
+    update
+}
+
+
+fn attach_info_from_env() -> Option<ApiInfo> {
+    let port = std::env::var("ATARAX_ATTACH_PORT").ok()?.trim().parse().ok()?;
+    let token = std::env::var("ATARAX_ATTACH_TOKEN").ok()?;
+    Some(ApiInfo {
+        port,
+        token,
+        status: "ready".to_string(),
+    })
+}
+
+
+async fn reachable_attach_target(
+    client: &reqwest::Client,
+    attach_info: Option<ApiInfo>,
+) -> Option<ApiInfo> {
+    let api_info = attach_info?;
+    match query_backend(client, &api_info, "/v1/health", HEALTH_CHECK_TIMEOUT).await {
+        Ok(_) => Some(api_info),
+        Err(e) => {
+            eprintln!(
+                "Could not attach to running backend on port {} ({}), starting sidecar instead.",
+                api_info.port, e
+            );
+            None
+        }
     }
 }
 
 
+fn attach_to_running_backend(app_handle: &AppHandle, api_info: ApiInfo) {
+    let api_state: State<ApiState> = app_handle.state();
+    let api_process_state: State<ApiProcess> = app_handle.state();
+    let attached: State<AttachedBackend> = app_handle.state();
+    let runtime_info_cache: State<RuntimeInfoCache> = app_handle.state();
+    let port = api_info.port;
+
+    // Swap the connection details while holding the process lock so a bundled
+    // sidecar finishing its handshake cannot overwrite them.
+    let bundled_child = {
+        let mut process_guard = api_process_state.0.lock().unwrap();
+        let child = process_guard.take();
+        *attached.0.lock().unwrap() = true;
+        api_state.set_info(api_info);
+        child
+    };
+    runtime_info_cache.0.lock().unwrap().take();
+
+    if let Some(child) = bundled_child {
+        println!("Stopping bundled sidecar now that an external backend is attached.");
+        if let Err(e) = child.kill() {
+            eprintln!("Failed to kill sidecar: {}", e);
+        }
+    }
+
+    println!("Attached to running backend on port {}.", port);
+    emit_replayable(app_handle, "backend-ready", BackendReady { port });
+}
+
+
+#[tauri::command]
+async fn attach_to_backend(port: u16, token: String, app_handle: AppHandle) -> Result<(), String> {
+    let api_info = ApiInfo {
+        port,
+        token,
+        status: "ready".to_string(),
+    };
+    let http: State<HttpClient> = app_handle.state();
+    let health_history: State<HealthHistory> = app_handle.state();
+
+    let started = Instant::now();
+    let result = query_backend(&http.client(), &api_info, "/v1/health", HEALTH_CHECK_TIMEOUT).await;
+    health_history.record("attach", port, started.elapsed(), &result);
+    result?;
+
+    attach_to_running_backend(&app_handle, api_info);
+    Ok(())
+}
+
+
+fn spawn_python_sidecar(app_handle: AppHandle, executable_override: Option<PathBuf>) {
+    let task_handle = app_handle.clone();
+    spawn_background(&app_handle, async move {
+        if let Err(e) = start_python_sidecar(task_handle.clone(), executable_override).await {
+            let err_msg = format!("Failed to start Python sidecar: {}", e);
+            eprintln!("{}", err_msg);
+            emit_replayable(&task_handle, "sidecar-error", err_msg);
+        }
+    });
+}
+
+
 async fn start_python_sidecar(
     app_handle: AppHandle,
+    executable_override: Option<PathBuf>,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    println!("Resolving path for Python sidecar executable 'api'...");
-    
     let api_state: State<ApiState> = app_handle.state();
     let api_process_state: State<ApiProcess> = app_handle.state();
+    let timings: State<StartupTimings> = app_handle.state();
+    let log_capture: State<SidecarLogCapture> = app_handle.state();
+    let log_file: State<SidecarLogFile> = app_handle.state();
+    let runtime_info_cache: State<RuntimeInfoCache> = app_handle.state();
+    let attached: State<AttachedBackend> = app_handle.state();
+    timings.update(|phases| {
+        *phases = StartupPhases {
+            started: Some(Instant::now()),
+            ..Default::default()
+        }
+    });
 
-    let executable_name = if cfg!(target_os = "windows") {
-        "api.exe"
-    } else {
-        "api"
+    let is_override = executable_override.is_some();
+    let executable_path = match executable_override {
+        Some(path) => path,
+        None => {
+            println!("Resolving path for Python sidecar executable 'api'...");
+            match resolve_sidecar_path(&app_handle) {
+                Ok(path) if path.is_file() => path,
+                Ok(path) => {
+                    let attempted = path.to_string_lossy().into_owned();
+                    return Err(report_missing_sidecar(&app_handle, attempted).into());
+                }
+                Err(e) => {
+                    eprintln!("Failed to resolve sidecar path: {}", e);
+                    let attempted = format!("py_src/{}", sidecar_executable_name());
+                    return Err(report_missing_sidecar(&app_handle, attempted).into());
+                }
+            }
+        }
     };
 
-    let resource_path = format!("py_src/{}", executable_name);
-    
-    let executable_path = app_handle
-        .path()
-        .resolve(&resource_path, tauri::path::BaseDirectory::Resource)?;
+    // Debug reloads point at freshly rebuilt binaries that never match the
+    // bundled hash, so only the packaged sidecar is verified.
+    let integrity_policy = IntegrityPolicy::from_env();
+    if is_override && integrity_policy != IntegrityPolicy::Off {
+        println!("Skipping sidecar integrity check for debug override: {:?}", executable_path);
+    } else if integrity_policy != IntegrityPolicy::Off {
+        let report = check_sidecar_integrity(&app_handle, executable_path.clone()).await?;
+        match report.matches {
+            Some(true) => println!("Sidecar integrity verified: {}", report.actual_sha256),
+            Some(false) => {
+                let message = format!(
+                    "Sidecar integrity check failed: expected {}, found {}",
+                    report.expected_sha256.clone().unwrap_or_default(),
+                    report.actual_sha256
+                );
+                emit_replayable(&app_handle, "sidecar-integrity-failed", report);
+                if integrity_policy == IntegrityPolicy::Enforce {
+                    return Err(message.into());
+                }
+                eprintln!("{}", message);
+            }
+            None => {
+                let message = format!(
+                    "No expected hash bundled for the sidecar; computed {}",
+                    report.actual_sha256
+                );
+                if integrity_policy == IntegrityPolicy::Enforce {
+                    emit_replayable(&app_handle, "sidecar-integrity-failed", report);
+                    return Err(message.into());
+                }
+                eprintln!("{}", message);
+            }
+        }
+    }
     
     println!("Starting Python sidecar from: {:?}", executable_path);
 
     let (mut rx, child) = app_handle.shell().command(&executable_path).spawn()?;
-    
-    *api_process_state.0.lock().unwrap() = Some(child);
+    timings.update(|phases| phases.spawned = Some(Instant::now()));
+
+    let pid = child.pid();
+    {
+        let mut process_guard = api_process_state.0.lock().unwrap();
+        if *attached.0.lock().unwrap() {
+            println!("An external backend is already attached; stopping the new sidecar.");
+            child.kill()?;
+            return Ok(());
+        }
+        *process_guard = Some(child);
+    }
 
     println!("Waiting for Python backend to emit connection details...");
     
@@ -114,23 +1976,65 @@ async fn start_python_sidecar(
     while let Some(event) = rx.recv().await {
         match event {
             CommandEvent::Stdout(line) => {
+                timings.update(|phases| {
+                    phases.first_stdout.get_or_insert_with(Instant::now);
+                });
                 if let Ok(line_str) = String::from_utf8(line) {
-                    if !handshake_complete {
-                        if let Ok(api_info) = serde_json::from_str::<ApiInfo>(&line_str) {
-                            if api_info.status == "ready" {
+                    if let Some(api_info) = parse_ready_handshake(&line_str) {
+                        log_file.write_line(
+                            "handshake",
+                            &format!("backend ready on port {} (token redacted)", api_info.port),
+                        );
+
+                        let process_guard = api_process_state.0.lock().unwrap();
+                        if process_guard.as_ref().map(|child| child.pid()) != Some(pid) {
+                            println!("Ignoring ready handshake from a sidecar that is no longer current.");
+                            continue;
+                        }
+
+                        match apply_ready_handshake(&api_state, &mut handshake_complete, api_info.clone()) {
+                            HandshakeUpdate::First => {
                                 println!("Backend is ready. Port: {}, Token acquired.", api_info.port);
-                                api_state.set_info(api_info);
-                                handshake_complete = true;
+                                timings.update(|phases| phases.handshake = Some(Instant::now()));
+                                spawn_background(
+                                    &app_handle,
+                                    confirm_backend_health(app_handle.clone(), api_info.clone()),
+                                );
+                            }
+                            HandshakeUpdate::Repeated => {
+                                println!("Ignoring repeated ready handshake for port {}.", api_info.port);
+                                continue;
+                            }
+                            HandshakeUpdate::Changed => {
+                                println!(
+                                    "Backend re-announced itself on port {}. Updating connection details.",
+                                    api_info.port
+                                );
+                                runtime_info_cache.0.lock().unwrap().take();
                             }
                         }
-                    } else {
+
+                        emit_replayable(
+                            &app_handle,
+                            "backend-ready",
+                            BackendReady { port: api_info.port },
+                        );
+                        drop(process_guard);
+                        continue;
+                    }
+
+                    if handshake_complete {
                         println!("Python sidecar (stdout): {}", line_str.trim());
                     }
+                    log_capture.write_line("stdout", line_str.trim());
+                    log_file.write_line("stdout", line_str.trim());
                 }
             }
             CommandEvent::Stderr(line) => {
                 if let Ok(line_str) = String::from_utf8(line) {
                     eprintln!("Python sidecar (stderr): {}", line_str.trim());
+                    log_capture.write_line("stderr", line_str.trim());
+                    log_file.write_line("stderr", line_str.trim());
                 }
             }
             CommandEvent::Error(line) => {
@@ -138,7 +2042,15 @@ async fn start_python_sidecar(
             }
             CommandEvent::Terminated(payload) => {
                 eprintln!("Python sidecar terminated with status: {:?}", payload);
-                if !handshake_complete {
+                let mut process_guard = api_process_state.0.lock().unwrap();
+                let is_current = process_guard.as_ref().map(|child| child.pid()) == Some(pid);
+                if is_current {
+                    process_guard.take();
+                }
+                drop(process_guard);
+                if !is_current {
+                    println!("Sidecar with pid {} was stopped deliberately.", pid);
+                } else if !handshake_complete {
                     return Err("Sidecar process terminated before it became ready.".into());
                 }
                 break; 
@@ -157,23 +2069,60 @@ pub fn run() {
         .plugin(tauri_plugin_shell::init())
         .manage(ApiState::default())
         .manage(ApiProcess(Mutex::new(None)))
+        .manage(StartupTimings::default())
+        .manage(HandshakeConfig(Mutex::new(HandshakeSettings::from_env())))
+        .manage(HttpClient::new(HttpClientConfig::from_env()).unwrap_or_else(|e| {
+            eprintln!("{}. Falling back to the default HTTP client settings.", e);
+            HttpClient::new(HttpClientConfig::default()).expect("failed to build backend HTTP client")
+        }))
+        .manage(RuntimeInfoCache::default())
+        .manage(AttachedBackend::default())
+        .manage(HealthHistory::default())
+        .manage(SidecarHashCache::default())
+        .manage(EventReplayBuffer::default())
+        .manage(ShutdownSignal::default())
+        .manage(SidecarLogCapture::default())
         .invoke_handler(tauri::generate_handler![
             get_api_info,
-            stop_python_sidecar
+            stop_python_sidecar,
+            run_diagnostics,
+            get_startup_timings,
+            reload_sidecar_from_path,
+            get_backend_runtime_info,
+            start_sidecar_log_capture,
+            stop_sidecar_log_capture,
+            warm_up_connection,
+            get_sidecar_log_path,
+            verify_sidecar_integrity,
+            get_http_client_config,
+            set_http_client_config,
+            download_artifact,
+            replay_events,
+            set_handshake_timeout,
+            create_support_bundle,
+            attach_to_backend,
+            validate_config,
+            estimate_tokens
         ])
         .setup(|app| {
+            app.manage(SidecarLogFile::from_env(app.handle()));
+
             let app_handle = app.handle().clone();
-            async_runtime::spawn(async move {
-                if let Err(e) = start_python_sidecar(app_handle.clone()).await {
-                    let err_msg = format!("Failed to start Python sidecar: {}", e);
-                    eprintln!("{}", err_msg);
-                    let _ = app_handle.emit("sidecar-error", err_msg);
+            let task_handle = app_handle.clone();
+            spawn_background(&app_handle, async move {
+                let http: State<HttpClient> = task_handle.state();
+                match reachable_attach_target(&http.client(), attach_info_from_env()).await {
+                    Some(api_info) => attach_to_running_backend(&task_handle, api_info),
+                    None => spawn_python_sidecar(task_handle.clone(), None),
                 }
             });
             Ok(())
         })
         .on_window_event(|window, event| {
             if let WindowEvent::Destroyed = event {
+                let shutdown: State<ShutdownSignal> = window.state();
+                shutdown.0.cancel();
+
                 println!("Window closed, terminating sidecar process...");
                 let state: State<ApiProcess> = window.state();
                 let child_to_kill = state.0.lock().unwrap().take();
@@ -184,8 +2133,443 @@ pub fn run() {
                         println!("Sidecar process terminated successfully.");
                     }
                 }
+                let log_capture: State<SidecarLogCapture> = window.state();
+                log_capture.stop();
             }
         })
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
-}
\ No newline at end of file
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::AsyncReadExt;
+
+    fn api_info(port: u16) -> ApiInfo {
+        ApiInfo {
+            port,
+            token: "test-token".to_string(),
+            status: "ready".to_string(),
+        }
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("ataraxai-test-{}-{}", std::process::id(), name))
+    }
+
+    fn closed_port() -> u16 {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        listener.local_addr().unwrap().port()
+    }
+
+    async fn mock_backend(status: &'static str, body: &'static str) -> u16 {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut request = [0u8; 4096];
+                let _ = socket.read(&mut request).await;
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+        port
+    }
+
+    #[tokio::test]
+    async fn diagnostics_stop_at_http_step_when_backend_refuses() {
+        let client = build_http_client(&HttpClientConfig::default()).unwrap();
+        let report = diagnose_backend(
+            Some(4242),
+            false,
+            Some(api_info(closed_port())),
+            &client,
+            HandshakeSettings::default(),
+        )
+        .await;
+
+        assert!(!report.success);
+        assert_eq!(report.reached_step, "http_health");
+        assert!(!report.steps.last().unwrap().passed);
+    }
+
+    #[tokio::test]
+    async fn reachable_backend_is_attached_instead_of_spawning() {
+        let port = mock_backend("200 OK", r#"{"status":"ok","message":"healthy"}"#).await;
+        let client = build_http_client(&HttpClientConfig::default()).unwrap();
+
+        let target = reachable_attach_target(&client, Some(api_info(port))).await;
+        assert_eq!(target.map(|info| info.port), Some(port));
+
+        let target = reachable_attach_target(&client, Some(api_info(closed_port()))).await;
+        assert!(target.is_none());
+        assert!(reachable_attach_target(&client, None).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn diagnostics_accept_an_attached_backend_without_a_sidecar() {
+        let port = mock_backend("200 OK", r#"{"status":"ok","message":"healthy"}"#).await;
+        let client = build_http_client(&HttpClientConfig::default()).unwrap();
+        let report =
+            diagnose_backend(None, true, Some(api_info(port)), &client, HandshakeSettings::default()).await;
+
+        assert!(report.success);
+        assert!(report.steps[0].passed);
+        assert!(report.steps[0].detail.contains("Attached to external backend"));
+    }
+
+    #[test]
+    fn config_validation_reports_every_problem() {
+        let env: HashMap<String, String> = [
+            ("ATARAX_HANDSHAKE_TIMEOUT_SECS", "0"),
+            ("ATARAX_HTTP_TCP_KEEPALIVE_SECS", "soon"),
+            ("ATARAX_HTTP_POOL_MAX_IDLE_PER_HOST", "-1"),
+            ("ATARAX_SIDECAR_INTEGRITY", "paranoid"),
+            ("ATARAX_SIDECAR_LOG", "1"),
+            ("ATARAX_ATTACH_PORT", "8000"),
+            ("ATARAX_HTTP_USER_AGENT", "AtaraxAI/1.0\r"),
+            ("ATARAX_HTTP2_PRIOR_KNOWLEDGE", "true"),
+        ]
+        .into_iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+
+        let issues = collect_config_issues(&env, HandshakeSettings::default(), true, false);
+        let reported: Vec<&str> = issues.iter().map(|issue| issue.setting.as_str()).collect();
+
+        for setting in [
+            "ATARAX_HANDSHAKE_TIMEOUT_SECS",
+            "ATARAX_HTTP_TCP_KEEPALIVE_SECS",
+            "ATARAX_HTTP_POOL_MAX_IDLE_PER_HOST",
+            "ATARAX_SIDECAR_INTEGRITY",
+            "ATARAX_SIDECAR_LOG",
+            "ATARAX_ATTACH_PORT",
+            "ATARAX_HTTP_USER_AGENT",
+            "ATARAX_HTTP2_PRIOR_KNOWLEDGE",
+        ] {
+            assert!(reported.contains(&setting), "{} was not reported: {:?}", setting, issues);
+        }
+    }
+
+    #[test]
+    fn config_validation_handles_huge_handshake_timeouts() {
+        let settings = HandshakeSettings {
+            timeout_secs: u64::MAX,
+            poll_ms: 1000,
+        };
+        assert!(collect_config_issues(&HashMap::new(), settings, true, true).is_empty());
+    }
+
+    #[test]
+    fn captured_lines_reach_the_file_without_stopping() {
+        let path = temp_path("capture.log");
+        let _ = std::fs::remove_file(&path);
+        let capture = SidecarLogCapture::default();
+
+        capture.start(path.clone()).unwrap();
+        capture.write_line("stdout", "model loaded");
+        capture.write_line("stderr", "Traceback (most recent call last):");
+
+        let contents = std::fs::read_to_string(&path).unwrap();
+        assert!(contents.contains("[stdout] model loaded"));
+        assert!(contents.contains("[stderr] Traceback (most recent call last):"));
+
+        assert_eq!(capture.stop(), Some(path.clone()));
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn artifact_download_writes_the_fixture() {
+        let fixture = "artifact fixture contents";
+        let port = mock_backend("200 OK", fixture).await;
+        let client = build_http_client(&HttpClientConfig::default()).unwrap();
+        let url = validate_artifact_url(
+            &format!("http://127.0.0.1:{}/v1/artifacts/fixture.bin", port),
+            &api_info(port),
+        )
+        .unwrap();
+        let dest = temp_path("fixture.bin");
+        let dest_path = validate_download_dest(dest.to_str().unwrap()).unwrap();
+
+        let mut progress = Vec::new();
+        let bytes = fetch_artifact(&client, url, "test-token", &dest_path, |downloaded, total| {
+            progress.push((downloaded, total))
+        })
+        .await
+        .unwrap();
+
+        let expected = fixture.len() as u64;
+        assert_eq!(bytes, expected);
+        assert_eq!(std::fs::read_to_string(&dest_path).unwrap(), fixture);
+        assert_eq!(progress.last(), Some(&(expected, Some(expected))));
+        assert!(!temp_path("fixture.bin.part").exists());
+        let _ = std::fs::remove_file(&dest_path);
+    }
+
+    #[test]
+    fn download_destination_must_be_an_absolute_file_path() {
+        assert!(validate_download_dest("relative/model.bin").is_err());
+        assert!(validate_download_dest(std::env::temp_dir().to_str().unwrap()).is_err());
+        assert!(validate_download_dest(temp_path("missing-dir/model.bin").to_str().unwrap()).is_err());
+        assert!(validate_download_dest(temp_path("model.bin").to_str().unwrap()).is_ok());
+    }
+
+    #[test]
+    fn ready_handshakes_are_classified() {
+        let first = api_info(8000);
+        let moved = ApiInfo {
+            port: 8001,
+            ..first.clone()
+        };
+        let rotated = ApiInfo {
+            token: "rotated-token".to_string(),
+            ..first.clone()
+        };
+
+        assert_eq!(classify_ready_handshake(false, None, &first), HandshakeUpdate::First);
+        assert_eq!(classify_ready_handshake(true, Some(&first), &first), HandshakeUpdate::Repeated);
+        assert_eq!(classify_ready_handshake(true, Some(&first), &moved), HandshakeUpdate::Changed);
+        assert_eq!(classify_ready_handshake(true, Some(&first), &rotated), HandshakeUpdate::Changed);
+    }
+
+    #[test]
+    fn second_ready_handshake_updates_the_connection_details() {
+        let state = ApiState::default();
+        let mut handshake_complete = false;
+        let handshake = |line: &str| parse_ready_handshake(line).unwrap();
+
+        let first = handshake(r#"{"port": 8000, "token": "first", "status": "ready"}"#);
+        let second = handshake(r#"{"port": 8123, "token": "second", "status": "ready"}"#);
+
+        assert_eq!(
+            apply_ready_handshake(&state, &mut handshake_complete, first.clone()),
+            HandshakeUpdate::First
+        );
+        assert!(handshake_complete);
+        assert_eq!(
+            apply_ready_handshake(&state, &mut handshake_complete, first),
+            HandshakeUpdate::Repeated
+        );
+        assert_eq!(state.get_info().unwrap().port, 8000);
+        assert_eq!(
+            apply_ready_handshake(&state, &mut handshake_complete, second),
+            HandshakeUpdate::Changed
+        );
+
+        let info = state.get_info().unwrap();
+        assert_eq!(info.port, 8123);
+        assert_eq!(info.token, "second");
+    }
+
+    #[test]
+    fn sha256_of_a_fixture_file_matches_the_known_digest() {
+        let path = temp_path("hash-fixture.bin");
+        std::fs::write(&path, b"abc").unwrap();
+
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        let _ = std::fs::remove_file(&path);
+    }
+
+    #[tokio::test]
+    async fn health_checks_honor_the_short_timeout() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let port = listener.local_addr().unwrap().port();
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                connections.push(socket);
+            }
+        });
+
+        let client = build_http_client(&HttpClientConfig::default()).unwrap();
+        let started = Instant::now();
+        let error = fetch_backend_json(&client, &api_info(port), "/v1/health", Duration::from_millis(200))
+            .await
+            .unwrap_err();
+
+        assert!(error.contains("did not respond"), "{}", error);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+
+    #[derive(Debug, Default)]
+    struct RecordingBuilder {
+        pool_max_idle_per_host: Option<usize>,
+        pool_idle_timeout: Option<Duration>,
+        tcp_keepalive: Option<Duration>,
+        http2_prior_knowledge: bool,
+        user_agent: Option<String>,
+        default_headers: Option<reqwest::header::HeaderMap>,
+    }
+
+    impl ClientBuilderSettings for RecordingBuilder {
+        fn pool_max_idle_per_host(mut self, max_idle: usize) -> Self {
+            self.pool_max_idle_per_host = Some(max_idle);
+            self
+        }
+
+        fn pool_idle_timeout(mut self, idle_timeout: Duration) -> Self {
+            self.pool_idle_timeout = Some(idle_timeout);
+            self
+        }
+
+        fn tcp_keepalive(mut self, keepalive: Duration) -> Self {
+            self.tcp_keepalive = Some(keepalive);
+            self
+        }
+
+        fn http2_prior_knowledge(mut self) -> Self {
+            self.http2_prior_knowledge = true;
+            self
+        }
+
+        fn user_agent(mut self, user_agent: &str) -> Self {
+            self.user_agent = Some(user_agent.to_string());
+            self
+        }
+
+        fn default_headers(mut self, headers: reqwest::header::HeaderMap) -> Self {
+            self.default_headers = Some(headers);
+            self
+        }
+    }
+
+    #[test]
+    fn http_client_builder_receives_the_pool_settings() {
+        let config = HttpClientConfig {
+            pool_max_idle_per_host: Some(4),
+            pool_idle_timeout_secs: Some(30),
+            tcp_keepalive_secs: Some(60),
+            http2_prior_knowledge: true,
+            user_agent: Some("AtaraxAI-Test".to_string()),
+            custom_headers: HashMap::from([("X-Client".to_string(), "desktop".to_string())]),
+        };
+
+        let builder = config.apply(RecordingBuilder::default()).unwrap();
+        assert_eq!(builder.pool_max_idle_per_host, Some(4));
+        assert_eq!(builder.pool_idle_timeout, Some(Duration::from_secs(30)));
+        assert_eq!(builder.tcp_keepalive, Some(Duration::from_secs(60)));
+        assert!(builder.http2_prior_knowledge);
+        assert_eq!(builder.user_agent.as_deref(), Some("AtaraxAI-Test"));
+        assert_eq!(builder.default_headers.unwrap()["x-client"], "desktop");
+    }
+
+    #[tokio::test]
+    async fn background_tasks_exit_when_the_token_fires() {
+        let token = CancellationToken::new();
+        let task = tokio::spawn(until_cancelled(token.clone(), std::future::pending()));
+
+        token.cancel();
+        tokio::time::timeout(Duration::from_secs(1), task)
+            .await
+            .expect("background task ignored the shutdown token")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn warm_up_succeeds_against_a_reachable_backend() {
+        let port = mock_backend("200 OK", r#"{"status":"ok","message":"healthy"}"#).await;
+        let client = build_http_client(&HttpClientConfig::default()).unwrap();
+
+        assert_eq!(warm_up(&client, &api_info(port)).await, Ok("healthy".to_string()));
+    }
+
+    #[test]
+    fn health_history_keeps_the_most_recent_checks() {
+        let history = HealthHistory::default();
+        for port in 0..(HEALTH_HISTORY_CAPACITY as u16 + 5) {
+            history.record("warmup", port, Duration::from_millis(3), &Ok("healthy".to_string()));
+        }
+        history.record("diagnostics", 9000, Duration::ZERO, &Err("refused".to_string()));
+
+        let recent = history.recent();
+        assert_eq!(recent.len(), HEALTH_HISTORY_CAPACITY);
+        assert_eq!(recent[0].port, 6);
+        let last = recent.last().unwrap();
+        assert!(!last.healthy);
+        assert_eq!(last.detail, "refused");
+    }
+
+    #[test]
+    fn token_estimates_fall_in_a_reasonable_range() {
+        assert_eq!(approximate_token_count(""), 0);
+        assert!((3..=5).contains(&approximate_token_count("Hello, world!")));
+
+        let pangram = "The quick brown fox jumps over the lazy dog.";
+        assert!((8..=12).contains(&approximate_token_count(pangram)));
+
+        let paragraph = pangram.repeat(100);
+        assert!((800..=1200).contains(&approximate_token_count(&paragraph)));
+
+        assert!((10..=20).contains(&approximate_token_count("人工智能正在改变世界")));
+        assert!((7..=14).contains(&approximate_token_count("こんにちは世界")));
+        assert!((5..=10).contains(&approximate_token_count("안녕하세요 세계")));
+    }
+
+    #[tokio::test]
+    async fn http2_prior_knowledge_fails_against_an_http1_backend() {
+        let port = mock_backend("200 OK", r#"{"status":"ok","message":"healthy"}"#).await;
+        let config = HttpClientConfig {
+            http2_prior_knowledge: true,
+            ..Default::default()
+        };
+        let client = build_http_client(&config).unwrap();
+
+        assert!(query_backend(&client, &api_info(port), "/v1/health", HEALTH_CHECK_TIMEOUT)
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn config_validation_and_loaders_accept_the_same_values() {
+        for value in ["45", " 45 ", "45\r", "0", "-5", "soon", ""] {
+            let env: HashMap<String, String> = [
+                "ATARAX_HANDSHAKE_TIMEOUT_SECS",
+                "ATARAX_HTTP_POOL_IDLE_TIMEOUT_SECS",
+                "ATARAX_HTTP_TCP_KEEPALIVE_SECS",
+            ]
+            .into_iter()
+            .map(|key| (key.to_string(), value.to_string()))
+            .collect();
+
+            let issues = collect_config_issues(&env, HandshakeSettings::default(), true, true);
+            let rejected = |key: &str| issues.iter().any(|issue| issue.setting == key);
+            let handshake = HandshakeSettings::from_vars(&env);
+            let http = HttpClientConfig::from_vars(&env);
+
+            assert_eq!(
+                rejected("ATARAX_HANDSHAKE_TIMEOUT_SECS"),
+                handshake.timeout_secs == DEFAULT_HANDSHAKE_TIMEOUT_SECS,
+                "{:?}",
+                value
+            );
+            assert_eq!(rejected("ATARAX_HTTP_POOL_IDLE_TIMEOUT_SECS"), http.pool_idle_timeout_secs.is_none(), "{:?}", value);
+            assert_eq!(rejected("ATARAX_HTTP_TCP_KEEPALIVE_SECS"), http.tcp_keepalive_secs.is_none(), "{:?}", value);
+        }
+    }
+
+    #[test]
+    fn sidecar_hash_cache_is_keyed_by_path_and_modification_time() {
+        let cache = SidecarHashCache::default();
+        let path = PathBuf::from("/opt/ataraxai/py_src/api");
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+
+        cache.store(path.clone(), modified, "abc123".to_string());
+        assert_eq!(cache.get(&path, modified), Some("abc123".to_string()));
+        assert_eq!(cache.get(&path, modified + Duration::from_secs(1)), None);
+        assert_eq!(cache.get(Path::new("/tmp/api"), modified), None);
+
+        cache.clear();
+        assert_eq!(cache.get(&path, modified), None);
+    }
+}